@@ -1,21 +1,23 @@
 use std::collections::LinkedList;
+use std::error::Error;
+use std::fmt;
 
 use crate::lexer::Token;
 
 #[derive(Debug)]
-pub enum ASTree {
-    LEAF { token: Token },
-    LIST { token_list: LinkedList<Token> },
+pub enum ASTree<'src> {
+    LEAF { token: Token<'src> },
+    LIST { token_list: LinkedList<Token<'src>> },
 }
 
-impl ASTree {
-    pub fn child(&self, i: usize) -> Result<&Token, String> {
+impl<'src> ASTree<'src> {
+    pub fn child(&self, i: usize) -> Result<&Token<'src>, AstError> {
         match self {
             ASTree::LEAF { .. } => {
-                Err("Out of bounds!".to_string())
+                Err(AstError::OutOfBounds { index: i, len: 0 })
             }
             ASTree::LIST { token_list } => {
-                token_list.iter().nth(i).ok_or("Out of bounds!".to_string())
+                token_list.iter().nth(i).ok_or(AstError::OutOfBounds { index: i, len: token_list.len() })
             }
         }
     }
@@ -31,33 +33,51 @@ impl ASTree {
         }
     }
 
-    pub fn children(&self) -> impl Iterator<Item=&Token> {
+    pub fn children(&self) -> impl Iterator<Item=&Token<'src>> {
         ASTreeIter{ value: self, index: 0}
     }
 
-    pub fn location(&self) -> String {
+    pub fn location(&self) -> Result<String, AstError> {
         match self {
             ASTree::LEAF { token } => {
-                Self::get_location_from_token(token)
+                Ok(Self::get_location_from_token(token))
             }
             ASTree::LIST { token_list } => {
-                Self::get_location_from_token(token_list.iter().nth(0).unwrap())
+                let token = token_list.iter().nth(0).ok_or(AstError::OutOfBounds { index: 0, len: 0 })?;
+                Ok(Self::get_location_from_token(token))
             }
         }
     }
 
-    pub fn get_location_from_token(token: &Token) -> String {
-        format!("at line: {}", token.get_line_number())
+    pub fn get_location_from_token(token: &Token<'src>) -> String {
+        let span = token.get_span();
+        format!("line {}, column {}", span.line, span.column)
     }
 }
 
-pub struct ASTreeIter<'a> {
-    value: &'a ASTree,
+impl fmt::Display for ASTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.location().unwrap_or_else(|_| "unknown location".to_string());
+        match self {
+            ASTree::LEAF { token } => writeln!(f, "LEAF at {} -> {}", location, token),
+            ASTree::LIST { .. } => {
+                writeln!(f, "LIST ({} children) at {}", self.children_number(), location)?;
+                for token in self.children() {
+                    writeln!(f, "  {}", token)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub struct ASTreeIter<'a, 'src> {
+    value: &'a ASTree<'src>,
     index: usize,
 }
 
-impl<'a> Iterator for ASTreeIter<'a> {
-    type Item = &'a Token;
+impl<'a, 'src> Iterator for ASTreeIter<'a, 'src> {
+    type Item = &'a Token<'src>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match *self.value {
@@ -70,35 +90,55 @@ impl<'a> Iterator for ASTreeIter<'a> {
     }
 }
 
+/// Errors produced while navigating an [`ASTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstError {
+    OutOfBounds { index: usize, len: usize },
+}
+
+impl fmt::Display for AstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstError::OutOfBounds { index, len } => {
+                write!(f, "child index {} out of bounds (len {})", index, len)
+            }
+        }
+    }
+}
+
+impl Error for AstError {}
+
 #[cfg(test)]
 mod tests {
     use std::collections::LinkedList;
-    use std::os::unix::raw::time_t;
-    use regex::internal::Input;
     use ASTree::LEAF;
     use Token::IDENTIFIER;
-    use crate::ast::ASTree;
+    use crate::ast::{AstError, ASTree};
     use crate::ast::ASTree::LIST;
-    use crate::lexer::{Token, TokenBase};
+    use crate::lexer::{Span, Token, TokenBase};
     use crate::lexer::tests::{assert_id_token, assert_number_token};
 
+    fn dummy_span() -> Span {
+        Span { start: 0, end: 0, line: 1, column: 1 }
+    }
+
     #[test]
     #[warn(non_snake_case)]
     fn ASTLeaf() -> () {
         let leaf = LEAF { token: IDENTIFIER {
-            token_base: TokenBase { text: "".to_string(), line_number: 1, }
+            token_base: TokenBase { text: "".into(), line_number: 1, span: dummy_span() }
         }};
 
         assert_eq!(leaf.children_number(), 0);
-        assert_eq!(leaf.child(0), Err("Out of bounds!".to_string()));
+        assert_eq!(leaf.child(0), Err(AstError::OutOfBounds { index: 0, len: 0 }));
         let mut children = leaf.children();
         assert_eq!(children.next(), None);
     }
 
     fn ASTList() -> () {
-        let token_number1 = Token::NUMBER { token_base: TokenBase { text: "1".to_string(), line_number: 1 }, number: 1 };
-        let token_plus = Token::IDENTIFIER { token_base: TokenBase { text: "+".to_string(), line_number: 1 } };
-        let token_number2 = Token::NUMBER { token_base: TokenBase { text: "2".to_string(), line_number: 1 }, number: 2 };
+        let token_number1 = Token::NUMBER { token_base: TokenBase { text: "1".into(), line_number: 1, span: dummy_span() }, number: 1 };
+        let token_plus = Token::IDENTIFIER { token_base: TokenBase { text: "+".into(), line_number: 1, span: dummy_span() } };
+        let token_number2 = Token::NUMBER { token_base: TokenBase { text: "2".into(), line_number: 1, span: dummy_span() }, number: 2 };
         let mut token_list = LinkedList::new();
         token_list.push_back(token_number1);
         token_list.push_back(token_plus);
@@ -113,4 +153,4 @@ mod tests {
         assert_number_token(children.next().unwrap(), 1, "2");
         assert_eq!(list.children_number(), 3);
     }
-}
\ No newline at end of file
+}
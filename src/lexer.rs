@@ -1,57 +1,105 @@
+use std::borrow::Cow;
 use std::collections::LinkedList;
-use std::fmt::{Debug, Formatter};
-use std::io::{BufRead, BufReader, Read};
-use std::string::ToString;
+use std::error::Error;
+use std::fmt;
+use std::io::{BufReader, Read};
 
-use regex::Regex;
+use regex::{Captures, Match, Regex};
 
-const TOKEN_REG_STR: &str = "\\s*((//.*)|([0-9]+)|(\"(\\\\\"|\\\\\\\\|\\\\n|[^\"])*\")|[A-Z_a-z][A-Z_a-z0-9]*|==|<=|>=|&&|\\|\\||[[:punct:]])?";
-const EOF_ERR_STR: &str = "Already reach EOF!";
+const TOKEN_REG_STR: &str = "\\s*((//.*)|([0-9]+\\.[0-9]+|[0-9]+)|(\"(\\\\\"|\\\\\\\\|\\\\n|[^\"])*\")|[A-Z_a-z][A-Z_a-z0-9]*|==|<=|>=|&&|\\|\\||[[:punct:]])?";
 
-pub struct Lexer<R: Read> {
+/// Which grammar the lexer is currently matching against. Pushed/popped as a
+/// stack so a string literal's `${ ... }` interpolation can temporarily
+/// switch back to ordinary code scanning and then resume string scanning
+/// right where it left off once the interpolation's closing `}` is found.
+///
+/// Note this stack only governs the embedded expressions: the outer string
+/// literal itself is still recognized in one shot by the quoted-string
+/// alternative in [`TOKEN_REG_STR`] (which requires an unescaped closing
+/// `"` on the same physical line to match at all) and then split on `${`
+/// by [`Lexer::emit_interpolated_string`]. A `"` occurring inside a `${ ... }`
+/// expression is not yet handled by a genuine per-mode grammar; it will end
+/// the outer match early rather than being scanned as a nested string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LexMode {
+    /// Ordinary code: numbers, identifiers, keywords, operators, strings.
+    /// `brace_depth` counts `{`/`}` nesting seen while this frame was
+    /// pushed to scan an interpolated expression, so an unrelated nested
+    /// `{ ... }` isn't mistaken for the interpolation's closing brace.
+    Normal { brace_depth: u32 },
+    /// Inside a string literal that contains at least one interpolation,
+    /// watching for the next `${` or the closing quote.
+    Interpolation,
+}
+
+/// Outcome of scanning a run of tokens in [`LexMode::Normal`].
+enum ScanResult {
+    /// Consumed the whole slice without incident.
+    Completed,
+    /// Stopped at the given byte offset (into the slice that was scanned)
+    /// because an interpolation's unbalanced closing `}` was found there.
+    StoppedBefore(usize),
+    /// A lex error was recorded in `pending_error`.
+    Errored,
+}
+
+/// Lexes a `&'src str` held entirely in memory, handing out tokens that
+/// borrow directly from it rather than allocating their own copies.
+pub struct Lexer<'src> {
     has_more: bool,
-    token_queue: LinkedList<Token>,
+    token_queue: LinkedList<Token<'src>>,
     line_number: usize,
-    reader: BufReader<R>,
+    byte_offset: usize,
+    rest: &'src str,
+    pending_error: Option<LexError>,
+    mode_stack: Vec<LexMode>,
 }
 
-impl<R: Read> Lexer<R> {
+impl<'src> Lexer<'src> {
     #[inline]
     fn get_token_regex() -> Regex {
         Regex::new(TOKEN_REG_STR).unwrap()
     }
 
-    pub fn new(reader: BufReader<R>) -> Self {
+    pub fn from_str(source: &'src str) -> Self {
         Self {
             has_more: true,
             token_queue: LinkedList::new(),
             line_number: 0,
-            reader,
+            byte_offset: 0,
+            rest: source,
+            pending_error: None,
+            mode_stack: vec![LexMode::Normal { brace_depth: 0 }],
         }
     }
 
-    pub fn read(&mut self) -> Result<Token, String> {
+    pub fn read(&mut self) -> Result<Token<'src>, LexError> {
         let result = self.fill_queue(0)?;
         return if result {
-            self.token_queue.pop_front().ok_or(String::from(""))
+            self.token_queue.pop_front().ok_or(LexError::UnexpectedEof)
         } else {
-            Err(String::from(EOF_ERR_STR))
+            Err(LexError::UnexpectedEof)
         };
     }
 
-    pub fn peek(&mut self, i: usize) -> Result<&Token, String> {
+    pub fn peek(&mut self, i: usize) -> Result<&Token<'src>, LexError> {
         let result = self.fill_queue(i)?;
         return if result && i < self.token_queue.len() {
             Ok(self.token_queue.iter().nth(i).unwrap())
         } else {
-            Err(String::from(EOF_ERR_STR))
+            Err(LexError::UnexpectedEof)
         };
     }
 
-    fn fill_queue(&mut self, i: usize) -> Result<bool, String> {
+    /// Fills the queue until it holds at least `i + 1` tokens. Any lexer
+    /// error is only surfaced once the tokens queued ahead of it are drained,
+    /// so a bad character doesn't swallow the valid tokens that precede it.
+    fn fill_queue(&mut self, i: usize) -> Result<bool, LexError> {
         while i >= self.token_queue.len() {
             if self.has_more {
-                self.read_line()?;
+                self.read_line();
+            } else if let Some(err) = self.pending_error.take() {
+                return Err(err);
             } else {
                 return Ok(false);
             }
@@ -59,77 +107,413 @@ impl<R: Read> Lexer<R> {
         Ok(true)
     }
 
-    fn read_line(&mut self) -> Result<(), String> {
-        let mut line = String::new();
-        let size = self.reader.read_line(&mut line)
-            .map_err(|err| err.to_string())?;
+    fn read_line(&mut self) {
+        if self.rest.is_empty() {
+            self.has_more = false;
+            let span = Span::zero_width(self.byte_offset, self.line_number, 1);
+            self.token_queue.push_back(Token::EOF { token_base: TokenBase { line_number: self.line_number, text: Cow::Borrowed(""), span } });
+            return;
+        }
 
-        match size {
-            0 => {
-                self.has_more = false;
-                self.token_queue.push_back(Token::EOF { token_base: TokenBase { line_number: self.line_number, text: "".to_string() } });
+        let (line, rest) = match self.rest.find('\n') {
+            Some(idx) => self.rest.split_at(idx + 1),
+            None => (self.rest, ""),
+        };
+        self.rest = rest;
+        self.line_number += 1;
+
+        let line_number = self.line_number;
+        let byte_offset = self.byte_offset;
+        if let ScanResult::Completed = self.scan_normal_tokens(line, line_number, byte_offset, 0, false) {
+            let content_len = line.trim_end_matches(['\r', '\n']).len();
+            let eol_span = Span::zero_width(byte_offset + content_len, self.line_number, content_len + 1);
+            self.byte_offset += line.len();
+            self.token_queue.push_back(Token::EOL { token_base: TokenBase { line_number: self.line_number, text: Cow::Borrowed(""), span: eol_span } });
+        }
+    }
+
+    /// Scans `text` in [`LexMode::Normal`], pushing tokens onto the queue.
+    ///
+    /// `base_offset`/`base_column` locate `text` within the overall source
+    /// (`text` may be a whole line, or just an interpolated expression cut
+    /// out of one). When `stop_on_unbalanced_close` is set, a `}` that isn't
+    /// balanced by an earlier `{` seen during this call is treated as the
+    /// end of an interpolation rather than an ordinary operator, and scanning
+    /// stops there so the caller can resume string-body scanning.
+    fn scan_normal_tokens(
+        &mut self,
+        text: &'src str,
+        line_number: usize,
+        base_offset: usize,
+        base_column: usize,
+        stop_on_unbalanced_close: bool,
+    ) -> ScanResult {
+        for cap in Self::get_token_regex().captures_iter(text) {
+            if cap.get(2).is_some() {
+                // line comment consumes the rest of the line
+                break;
             }
-            _ => {
-                self.line_number += 1;
-                for cap in Self::get_token_regex().captures_iter(line.as_str()) {
-                    if cap.get(1) == None || cap.get(2) != None {
-                        // spaces or comments
-                        break;
+
+            let whole = match cap.get(1) {
+                Some(whole) => whole,
+                None => {
+                    let consumed = cap.get(0).unwrap().end();
+                    let remainder = text[consumed..].trim_end_matches(['\r', '\n']);
+                    match remainder.chars().next() {
+                        Some(ch) => {
+                            let start = base_offset + consumed;
+                            let span = Span { start, end: start + ch.len_utf8(), line: line_number, column: base_column + consumed + 1 };
+                            self.pending_error = Some(LexError::UnexpectedCharacter { ch, span });
+                            self.has_more = false;
+                            return ScanResult::Errored;
+                        }
+                        None => break,
                     }
+                }
+            };
 
-                    let token: Token;
+            if stop_on_unbalanced_close {
+                if whole.as_str() == "{" {
+                    if let Some(LexMode::Normal { brace_depth }) = self.mode_stack.last_mut() {
+                        *brace_depth += 1;
+                    }
+                } else if whole.as_str() == "}" {
+                    let should_stop = match self.mode_stack.last_mut() {
+                        Some(LexMode::Normal { brace_depth }) if *brace_depth > 0 => {
+                            *brace_depth -= 1;
+                            false
+                        }
+                        _ => true,
+                    };
+                    if should_stop {
+                        return ScanResult::StoppedBefore(whole.start());
+                    }
+                }
+            }
 
-                    if cap.get(3) != None {
-                        let number = (&cap[3]).parse::<i32>().map_err(|err| err.to_string())?;
-                        token = Token::NUMBER { token_base: TokenBase { line_number: self.line_number, text: cap[3].to_string() }, number };
-                    } else if cap.get(4) != None {
-                        token = Token::STRING { token_base: TokenBase { line_number: self.line_number, text: cap[4][1..cap[4].len() - 1].to_string() } };
-                    } else {
-                        token = Token::IDENTIFIER { token_base: TokenBase { line_number: self.line_number, text: cap[1].to_string() } };
+            if cap.get(4).is_some() {
+                let whole_text = whole.as_str();
+                let content = &whole_text[1..whole_text.len() - 1];
+                let quote_start = base_offset + whole.start();
+                let quote_column = base_column + whole.start();
+                if content.contains("${") {
+                    if !self.emit_interpolated_string(content, quote_start, quote_column, line_number) {
+                        return ScanResult::Errored;
                     }
-                    self.token_queue.push_back(token);
+                } else {
+                    let span = Span { start: quote_start, end: quote_start + whole_text.len(), line: line_number, column: quote_column + 1 };
+                    let text = decode_string_escapes(content);
+                    self.token_queue.push_back(Token::STRING { token_base: TokenBase { line_number, text, span } });
                 }
-                self.token_queue.push_back(Token::EOL { token_base: TokenBase { line_number: self.line_number, text: "".to_string() } });
+                continue;
+            }
+
+            if whole.as_str() == "\"" {
+                let start = base_offset + whole.start();
+                let span = Span { start, end: start + 1, line: line_number, column: base_column + whole.start() + 1 };
+                self.pending_error = Some(LexError::UnterminatedString { span });
+                self.has_more = false;
+                return ScanResult::Errored;
+            }
+
+            match self.build_simple_token(&cap, whole, line_number, base_offset, base_column) {
+                Some(token) => self.token_queue.push_back(token),
+                None => return ScanResult::Errored,
             }
         }
+        ScanResult::Completed
+    }
 
-        Ok(())
+    /// Builds a token from a capture that's already been established to be
+    /// neither a comment, a string literal, nor an unterminated-quote error.
+    fn build_simple_token(
+        &mut self,
+        cap: &Captures<'src>,
+        whole: Match<'src>,
+        line_number: usize,
+        base_offset: usize,
+        base_column: usize,
+    ) -> Option<Token<'src>> {
+        let start = base_offset + whole.start();
+        let span = Span { start, end: start + whole.as_str().len(), line: line_number, column: base_column + whole.start() + 1 };
+
+        if cap.get(3).is_some() {
+            let text = cap.get(3).unwrap().as_str();
+            if text.contains('.') {
+                match text.parse::<f64>() {
+                    Ok(value) => Some(Token::FLOAT { token_base: TokenBase { line_number, text: Cow::Borrowed(text), span }, value }),
+                    Err(_) => {
+                        self.pending_error = Some(LexError::InvalidNumber { text: text.to_string(), span });
+                        self.has_more = false;
+                        None
+                    }
+                }
+            } else {
+                match text.parse::<i32>() {
+                    Ok(number) => Some(Token::NUMBER { token_base: TokenBase { line_number, text: Cow::Borrowed(text), span }, number }),
+                    Err(_) => {
+                        self.pending_error = Some(LexError::InvalidNumber { text: text.to_string(), span });
+                        self.has_more = false;
+                        None
+                    }
+                }
+            }
+        } else if let Some(kind) = Keyword::from_text(whole.as_str()) {
+            Some(Token::KEYWORD { token_base: TokenBase { line_number, text: Cow::Borrowed(whole.as_str()), span }, kind })
+        } else if is_identifier_shaped(whole.as_str()) {
+            Some(Token::IDENTIFIER { token_base: TokenBase { line_number, text: Cow::Borrowed(whole.as_str()), span } })
+        } else {
+            Some(Token::OPERATOR { token_base: TokenBase { line_number, text: Cow::Borrowed(whole.as_str()), span } })
+        }
+    }
+
+    /// Splits a string literal's content on `${ ... }` interpolations,
+    /// pushing `STRINGSTART`/`INTERPSTART`/`INTERPEND`/`STRINGEND`
+    /// boundary tokens (and the interpolated expressions' own tokens) onto
+    /// the queue. `quote_start`/`quote_column` locate the opening quote;
+    /// `content` is the literal's text with the surrounding quotes already
+    /// stripped. Returns `false` if a lex error aborted the scan.
+    ///
+    /// `content` has already been matched whole by the master token regex's
+    /// quoted-string alternative, so this is a post-hoc `find("${")` split
+    /// over that capture rather than a from-scratch mode-aware scan: it
+    /// handles single and multiple interpolations, nested `{ }` within an
+    /// embedded expression, and unterminated expressions/strings, but it
+    /// cannot recover if an embedded expression itself contains an
+    /// unescaped `"`, since the outer quote was already resolved before
+    /// this function ever runs.
+    fn emit_interpolated_string(&mut self, content: &'src str, quote_start: usize, quote_column: usize, line_number: usize) -> bool {
+        self.mode_stack.push(LexMode::Interpolation);
+
+        let mut pos = 0usize;
+        loop {
+            let chunk_start_offset = quote_start + 1 + pos;
+            let chunk_start_column = quote_column + 1 + pos;
+
+            let next_interp = content[pos..].find("${");
+            let chunk_end = match next_interp {
+                Some(idx) => pos + idx,
+                None => content.len(),
+            };
+            let chunk = &content[pos..chunk_end];
+            let chunk_span = Span {
+                start: chunk_start_offset,
+                end: chunk_start_offset + chunk.len(),
+                line: line_number,
+                column: chunk_start_column + 1,
+            };
+            let chunk_text = decode_string_escapes(chunk);
+            let token_base = TokenBase { line_number, text: chunk_text, span: chunk_span };
+
+            if next_interp.is_none() {
+                self.token_queue.push_back(Token::STRINGEND { token_base });
+                self.mode_stack.pop();
+                return true;
+            }
+            self.token_queue.push_back(Token::STRINGSTART { token_base });
+
+            let interp_start_offset = quote_start + 1 + chunk_end;
+            let interp_span = Span {
+                start: interp_start_offset,
+                end: interp_start_offset + 2,
+                line: line_number,
+                column: quote_column + 1 + chunk_end + 1,
+            };
+            self.token_queue.push_back(Token::INTERPSTART { token_base: TokenBase { line_number, text: Cow::Borrowed("${"), span: interp_span } });
+
+            let expr_start = chunk_end + 2; // past "${"
+            self.mode_stack.push(LexMode::Normal { brace_depth: 0 });
+
+            let expr_base_offset = quote_start + 1 + expr_start;
+            let expr_base_column = quote_column + 1 + expr_start;
+            let scan_result = self.scan_normal_tokens(&content[expr_start..], line_number, expr_base_offset, expr_base_column, true);
+            self.mode_stack.pop(); // pop the Normal frame pushed above for the expression
+
+            let rel_close_idx = match scan_result {
+                ScanResult::StoppedBefore(rel_close_idx) => rel_close_idx,
+                ScanResult::Completed => {
+                    // the embedded expression ran off the end of the string with no closing `}`
+                    self.pending_error = Some(LexError::UnterminatedString { span: chunk_span });
+                    self.has_more = false;
+                    self.mode_stack.pop(); // pop the Interpolation frame pushed at the top
+                    return false;
+                }
+                ScanResult::Errored => {
+                    self.mode_stack.pop(); // pop the Interpolation frame pushed at the top
+                    return false;
+                }
+            };
+
+            let close_offset_in_content = expr_start + rel_close_idx;
+            let close_abs_offset = quote_start + 1 + close_offset_in_content;
+            let close_span = Span {
+                start: close_abs_offset,
+                end: close_abs_offset + 1,
+                line: line_number,
+                column: quote_column + 1 + close_offset_in_content + 1,
+            };
+            self.token_queue.push_back(Token::INTERPEND { token_base: TokenBase { line_number, text: Cow::Borrowed("}"), span: close_span } });
+
+            pos = close_offset_in_content + 1;
+        }
+    }
+}
+
+/// Errors produced while tokenizing source text.
+#[derive(Debug)]
+pub enum LexError {
+    UnexpectedEof,
+    InvalidNumber { text: String, span: Span },
+    UnterminatedString { span: Span },
+    Io(std::io::Error),
+    UnexpectedCharacter { ch: char, span: Span },
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexError::UnexpectedEof => write!(f, "already reached EOF"),
+            LexError::InvalidNumber { text, span } => {
+                write!(f, "invalid number '{}' at line {}, column {}", text, span.line, span.column)
+            }
+            LexError::UnterminatedString { span } => {
+                write!(f, "unterminated string starting at line {}, column {}", span.line, span.column)
+            }
+            LexError::Io(err) => write!(f, "{}", err),
+            LexError::UnexpectedCharacter { ch, span } => {
+                write!(f, "unexpected character '{}' at line {}, column {}", ch, span.line, span.column)
+            }
+        }
+    }
+}
+
+impl Error for LexError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LexError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LexError {
+    fn from(err: std::io::Error) -> Self {
+        LexError::Io(err)
+    }
+}
+
+/// Owns a source buffer read from a `Read` stream so a [`Lexer`] can borrow
+/// from it. Use this when the source isn't already an in-memory `&str`;
+/// `Lexer::from_str` remains the primary, allocation-free entry point.
+pub struct SourceBuffer {
+    contents: String,
+}
+
+impl SourceBuffer {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, LexError> {
+        let mut contents = String::new();
+        BufReader::new(reader).read_to_string(&mut contents)?;
+        Ok(Self { contents })
+    }
+
+    pub fn lexer(&self) -> Lexer<'_> {
+        Lexer::from_str(&self.contents)
+    }
+}
+
+/// A byte-offset-and-position span into the source being lexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    fn zero_width(offset: usize, line: usize, column: usize) -> Self {
+        Self { start: offset, end: offset, line, column }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct TokenBase {
-    pub text: String,
+pub struct TokenBase<'src> {
+    pub text: Cow<'src, str>,
     pub line_number: usize,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum Token {
-    IDENTIFIER { token_base: TokenBase },
-    NUMBER { token_base: TokenBase, number: i32 },
-    STRING { token_base: TokenBase },
-    EOL { token_base: TokenBase },
-    EOF { token_base: TokenBase },
+pub enum Token<'src> {
+    IDENTIFIER { token_base: TokenBase<'src> },
+    KEYWORD { token_base: TokenBase<'src>, kind: Keyword },
+    OPERATOR { token_base: TokenBase<'src> },
+    NUMBER { token_base: TokenBase<'src>, number: i32 },
+    FLOAT { token_base: TokenBase<'src>, value: f64 },
+    STRING { token_base: TokenBase<'src> },
+    /// Leading (or middle) literal chunk of a string literal that contains
+    /// an interpolation, up to the next `${` it was split on.
+    STRINGSTART { token_base: TokenBase<'src> },
+    /// The `${` that opens an embedded expression inside a string literal.
+    INTERPSTART { token_base: TokenBase<'src> },
+    /// The `}` that closes an embedded expression inside a string literal.
+    INTERPEND { token_base: TokenBase<'src> },
+    /// Trailing literal chunk of an interpolated string literal, up to its
+    /// closing quote.
+    STRINGEND { token_base: TokenBase<'src> },
+    EOL { token_base: TokenBase<'src> },
+    EOF { token_base: TokenBase<'src> },
 }
 
-impl Token {
-    pub fn get_text(&self) -> String {
+impl<'src> Token<'src> {
+    pub fn get_text(&self) -> &str {
         match self {
             Token::IDENTIFIER { token_base, .. }
+            | Token::KEYWORD { token_base, .. }
+            | Token::OPERATOR { token_base, .. }
             | Token::NUMBER { token_base, .. }
+            | Token::FLOAT { token_base, .. }
             | Token::STRING { token_base, .. }
+            | Token::STRINGSTART { token_base, .. }
+            | Token::INTERPSTART { token_base, .. }
+            | Token::INTERPEND { token_base, .. }
+            | Token::STRINGEND { token_base, .. }
             | Token::EOL { token_base, .. }
-            | Token::EOF { token_base, .. } => { token_base.text.clone() }
+            | Token::EOF { token_base, .. } => { token_base.text.as_ref() }
         }
     }
 
     pub fn get_line_number(&self) -> usize {
         match self {
             Token::IDENTIFIER { token_base, .. }
+            | Token::KEYWORD { token_base, .. }
+            | Token::OPERATOR { token_base, .. }
+            | Token::NUMBER { token_base, .. }
+            | Token::FLOAT { token_base, .. }
+            | Token::STRING { token_base, .. }
+            | Token::STRINGSTART { token_base, .. }
+            | Token::INTERPSTART { token_base, .. }
+            | Token::INTERPEND { token_base, .. }
+            | Token::STRINGEND { token_base, .. }
+            | Token::EOL { token_base, .. }
+            | Token::EOF { token_base, .. } => { token_base.line_number }
+        }
+    }
+
+    pub fn get_span(&self) -> &Span {
+        match self {
+            Token::IDENTIFIER { token_base, .. }
+            | Token::KEYWORD { token_base, .. }
+            | Token::OPERATOR { token_base, .. }
             | Token::NUMBER { token_base, .. }
+            | Token::FLOAT { token_base, .. }
             | Token::STRING { token_base, .. }
+            | Token::STRINGSTART { token_base, .. }
+            | Token::INTERPSTART { token_base, .. }
+            | Token::INTERPEND { token_base, .. }
+            | Token::STRINGEND { token_base, .. }
             | Token::EOL { token_base, .. }
-            | Token::EOF { token_base, .. } => { token_base.line_number.clone() }
+            | Token::EOF { token_base, .. } => { &token_base.span }
         }
     }
 
@@ -139,126 +523,298 @@ impl Token {
             _ => { Err("Unsupported function!".to_string()) }
         }
     }
+
+    pub fn get_float(&self) -> Result<&f64, String> {
+        match self {
+            Token::FLOAT { value, .. } => { Ok(value) }
+            _ => { Err("Unsupported function!".to_string()) }
+        }
+    }
+
+    pub fn is_keyword(&self) -> bool {
+        matches!(self, Token::KEYWORD { .. })
+    }
+
+    pub fn is_operator(&self) -> bool {
+        matches!(self, Token::OPERATOR { .. })
+    }
+
+    /// Binding strength of this token if it's a binary operator, following
+    /// `|| = 1`, `&& = 2`, `== < > <= >= = 3`, `+ - = 4`, `* / % = 5`.
+    pub fn precedence(&self) -> Option<u8> {
+        match self {
+            Token::OPERATOR { token_base } => operator_precedence(token_base.text.as_ref()),
+            _ => None,
+        }
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Token::IDENTIFIER { .. } => "IDENTIFIER",
+            Token::KEYWORD { .. } => "KEYWORD",
+            Token::OPERATOR { .. } => "OPERATOR",
+            Token::NUMBER { .. } => "NUMBER",
+            Token::FLOAT { .. } => "FLOAT",
+            Token::STRING { .. } => "STRING",
+            Token::STRINGSTART { .. } => "STRINGSTART",
+            Token::INTERPSTART { .. } => "INTERPSTART",
+            Token::INTERPEND { .. } => "INTERPEND",
+            Token::STRINGEND { .. } => "STRINGEND",
+            Token::EOL { .. } => "EOL",
+            Token::EOF { .. } => "EOF",
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.get_span();
+        write!(
+            f,
+            "{} {:?} at line {}, column {} (bytes {}..{})",
+            self.kind_name(), self.get_text(), self.get_line_number(), span.column, span.start, span.end
+        )
+    }
+}
+
+fn operator_precedence(text: &str) -> Option<u8> {
+    match text {
+        "||" => Some(1),
+        "&&" => Some(2),
+        "==" | "<" | ">" | "<=" | ">=" => Some(3),
+        "+" | "-" => Some(4),
+        "*" | "/" | "%" => Some(5),
+        _ => None,
+    }
+}
+
+fn is_identifier_shaped(text: &str) -> bool {
+    text.chars().next().is_some_and(|ch| ch == '_' || ch.is_ascii_alphabetic())
+}
+
+/// Decodes `\n`, `\t`, `\"` and `\\` escapes in a string literal's contents
+/// (with the surrounding quotes already stripped). Borrows when there's
+/// nothing to decode, and only allocates once an escape is actually found.
+fn decode_string_escapes(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut decoded = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            decoded.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => decoded.push('\n'),
+            Some('t') => decoded.push('\t'),
+            Some('"') => decoded.push('"'),
+            Some('\\') => decoded.push('\\'),
+            Some(other) => {
+                decoded.push('\\');
+                decoded.push(other);
+            }
+            None => decoded.push('\\'),
+        }
+    }
+    Cow::Owned(decoded)
+}
+
+/// Reserved words recognized by the lexer; everything else shaped like an
+/// identifier is a plain `Token::IDENTIFIER`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    If,
+    Else,
+    While,
+    For,
+    Return,
+    Let,
+    Fn,
+    True,
+    False,
+    Break,
+    Continue,
+}
+
+impl Keyword {
+    fn from_text(text: &str) -> Option<Keyword> {
+        match text {
+            "if" => Some(Keyword::If),
+            "else" => Some(Keyword::Else),
+            "while" => Some(Keyword::While),
+            "for" => Some(Keyword::For),
+            "return" => Some(Keyword::Return),
+            "let" => Some(Keyword::Let),
+            "fn" => Some(Keyword::Fn),
+            "true" => Some(Keyword::True),
+            "false" => Some(Keyword::False),
+            "break" => Some(Keyword::Break),
+            "continue" => Some(Keyword::Continue),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use std::io::{BufReader, Read};
+    use std::io::Cursor;
 
-    use stringreader::StringReader;
+    use crate::lexer::{Keyword, LexError, Lexer, SourceBuffer, Span, Token};
 
-    use crate::lexer::{EOF_ERR_STR, Lexer, Token, TokenBase};
+    pub fn assert_number_token(token: &Token<'_>, line_number: usize, text: &str) {
+        match token {
+            Token::NUMBER { token_base, number } => {
+                assert_eq!(token_base.line_number, line_number);
+                assert_eq!(token_base.text, text);
+                assert_eq!(*number, text.parse::<i32>().unwrap());
+            }
+            _ => panic!("expected NUMBER token, got {:?}", token),
+        }
+    }
 
-    fn get_bufreader_from_str(string: &str) -> BufReader<StringReader> {
-        let string_reader = StringReader::new(&string);
-        BufReader::new(string_reader)
+    pub fn assert_string_token(token: &Token<'_>, line_number: usize, text: &str) {
+        match token {
+            Token::STRING { token_base } => {
+                assert_eq!(token_base.line_number, line_number);
+                assert_eq!(token_base.text, text);
+            }
+            _ => panic!("expected STRING token, got {:?}", token),
+        }
     }
 
-    pub fn assert_number_token(token: &Token, line_number: usize, text: &str) {
-        assert_eq!(*token, Token::NUMBER {
-            token_base: TokenBase { line_number, text: text.to_string() },
-            number: text.parse().unwrap(),
-        });
+    pub fn assert_float_token(token: &Token<'_>, line_number: usize, text: &str) {
+        match token {
+            Token::FLOAT { token_base, value } => {
+                assert_eq!(token_base.line_number, line_number);
+                assert_eq!(token_base.text, text);
+                assert_eq!(*value, text.parse::<f64>().unwrap());
+            }
+            _ => panic!("expected FLOAT token, got {:?}", token),
+        }
     }
 
-    pub fn assert_string_token(token: &Token, line_number: usize, text: &str) {
-        assert_eq!(*token, Token::STRING { token_base: TokenBase { line_number, text: text.to_string() } });
+    pub fn assert_id_token(token: &Token<'_>, line_number: usize, text: &str) {
+        match token {
+            Token::IDENTIFIER { token_base } => {
+                assert_eq!(token_base.line_number, line_number);
+                assert_eq!(token_base.text, text);
+            }
+            _ => panic!("expected IDENTIFIER token, got {:?}", token),
+        }
     }
 
-    pub fn assert_id_token(token: &Token, line_number: usize, text: &str) {
-        assert_eq!(*token, Token::IDENTIFIER { token_base: TokenBase { line_number, text: text.to_string() } });
+    pub fn assert_operator_token(token: &Token<'_>, line_number: usize, text: &str) {
+        match token {
+            Token::OPERATOR { token_base } => {
+                assert_eq!(token_base.line_number, line_number);
+                assert_eq!(token_base.text, text);
+            }
+            _ => panic!("expected OPERATOR token, got {:?}", token),
+        }
     }
 
-    fn assert_eol_token(token: &Token, line_number: usize) {
-        assert_eq!(*token, Token::EOL { token_base: TokenBase { line_number, text: "".to_string() } });
+    fn assert_eol_token(token: &Token<'_>, line_number: usize) {
+        match token {
+            Token::EOL { token_base } => assert_eq!(token_base.line_number, line_number),
+            _ => panic!("expected EOL token, got {:?}", token),
+        }
     }
 
-    fn assert_eof_token(token: &Token, line_number: usize) {
-        assert_eq!(*token, Token::EOF { token_base: TokenBase { line_number, text: "".to_string() } });
+    fn assert_eof_token(token: &Token<'_>, line_number: usize) {
+        match token {
+            Token::EOF { token_base } => assert_eq!(token_base.line_number, line_number),
+            _ => panic!("expected EOF token, got {:?}", token),
+        }
     }
 
-    fn read_and_assert_number_token<R: Read>(lexer: &mut Lexer<R>, line_number: usize, text: &str) {
+    fn read_and_assert_number_token(lexer: &mut Lexer<'_>, line_number: usize, text: &str) {
         let read_result = lexer.read();
         assert!(read_result.is_ok());
         let token = read_result.unwrap();
         assert_number_token(&token, line_number, text);
     }
 
-    fn read_and_assert_id_token<R: Read>(lexer: &mut Lexer<R>, line_number: usize, text: &str) {
+    fn read_and_assert_id_token(lexer: &mut Lexer<'_>, line_number: usize, text: &str) {
         let read_result = lexer.read();
         assert!(read_result.is_ok());
         let token = read_result.unwrap();
         assert_id_token(&token, line_number, text);
     }
 
-    fn read_and_assert_string_token<R: Read>(lexer: &mut Lexer<R>, line_number: usize, text: &str) {
+    fn read_and_assert_operator_token(lexer: &mut Lexer<'_>, line_number: usize, text: &str) {
+        let read_result = lexer.read();
+        assert!(read_result.is_ok());
+        let token = read_result.unwrap();
+        assert_operator_token(&token, line_number, text);
+    }
+
+    fn read_and_assert_string_token(lexer: &mut Lexer<'_>, line_number: usize, text: &str) {
         let read_result = lexer.read();
         assert!(read_result.is_ok());
         let token = read_result.unwrap();
         assert_string_token(&token, line_number, text);
     }
 
-    fn read_and_assert_eof_token<R: Read>(lexer: &mut Lexer<R>, line_number: usize) {
+    fn read_and_assert_eof_token(lexer: &mut Lexer<'_>, line_number: usize) {
         let read_result = lexer.read();
         assert!(read_result.is_ok());
         let token = read_result.unwrap();
         assert_eof_token(&token, line_number);
     }
 
-    fn peek_and_assert_eof_token<R: Read>(lexer: &mut Lexer<R>, i: usize, line_number: usize) {
+    fn peek_and_assert_eof_token(lexer: &mut Lexer<'_>, i: usize, line_number: usize) {
         let peek_result = lexer.peek(i);
         assert!(peek_result.is_ok());
         let token_ref = peek_result.unwrap();
         assert_eof_token(token_ref, line_number);
     }
 
-    fn read_and_assert_eol_token<R: Read>(lexer: &mut Lexer<R>, line_number: usize) {
+    fn read_and_assert_eol_token(lexer: &mut Lexer<'_>, line_number: usize) {
         let read_result = lexer.read();
         assert!(read_result.is_ok());
         let token = read_result.unwrap();
         assert_eol_token(&token, line_number);
     }
 
-    fn read_and_assert_error<R: Read>(lexer: &mut Lexer<R>, error_str: &str) {
+    fn read_and_assert_eof_error(lexer: &mut Lexer<'_>) {
         let read_result = lexer.read();
-        assert!(read_result.is_err());
-        assert_eq!(read_result.err().unwrap(), error_str);
+        assert!(matches!(read_result, Err(LexError::UnexpectedEof)));
     }
 
-    fn peek_and_assert_error<R: Read>(lexer: &mut Lexer<R>, i: usize, error_str: &str) {
+    fn peek_and_assert_eof_error(lexer: &mut Lexer<'_>, i: usize) {
         let peek_result = lexer.peek(i);
-        assert!(peek_result.is_err());
-        assert_eq!(peek_result.err().unwrap(), error_str);
+        assert!(matches!(peek_result, Err(LexError::UnexpectedEof)));
     }
 
     #[test]
     fn read_empty() {
-        let bufreader = get_bufreader_from_str("");
-        let mut lexer = Lexer::new(bufreader);
+        let mut lexer = Lexer::from_str("");
 
         read_and_assert_eof_token(&mut lexer, 0);
 
-        read_and_assert_error(&mut lexer, EOF_ERR_STR);
+        read_and_assert_eof_error(&mut lexer);
     }
 
     #[test]
     fn peek_empty() {
-        let bufreader = get_bufreader_from_str("");
-        let mut lexer = Lexer::new(bufreader);
+        let mut lexer = Lexer::from_str("");
 
         peek_and_assert_eof_token(&mut lexer, 0, 0);
 
         peek_and_assert_eof_token(&mut lexer, 0, 0);
 
-        peek_and_assert_error(&mut lexer, 1, EOF_ERR_STR);
+        peek_and_assert_eof_error(&mut lexer, 1);
 
-        peek_and_assert_error(&mut lexer, 2, EOF_ERR_STR);
+        peek_and_assert_eof_error(&mut lexer, 2);
     }
 
     #[test]
     fn read_one_number() {
-        let bufreader = get_bufreader_from_str("100");
-        let mut lexer = Lexer::new(bufreader);
+        let mut lexer = Lexer::from_str("100");
 
         read_and_assert_number_token(&mut lexer, 1, "100");
 
@@ -269,9 +825,7 @@ pub mod tests {
 
     #[test]
     fn read_one_id() {
-        let string_reader = StringReader::new("i");
-        let bufreader = BufReader::new(string_reader);
-        let mut lexer = Lexer::new(bufreader);
+        let mut lexer = Lexer::from_str("i");
 
         read_and_assert_id_token(&mut lexer, 1, "i");
 
@@ -282,9 +836,7 @@ pub mod tests {
 
     #[test]
     fn read_one_string() {
-        let string_reader = StringReader::new("\"hello\"");
-        let bufreader = BufReader::new(string_reader);
-        let mut lexer = Lexer::new(bufreader);
+        let mut lexer = Lexer::from_str("\"hello\"");
 
         read_and_assert_string_token(&mut lexer, 1, "hello");
 
@@ -295,13 +847,11 @@ pub mod tests {
 
     #[test]
     fn read_assign_number() {
-        let string_reader = StringReader::new("i = 1");
-        let bufreader = BufReader::new(string_reader);
-        let mut lexer = Lexer::new(bufreader);
+        let mut lexer = Lexer::from_str("i = 1");
 
         read_and_assert_id_token(&mut lexer, 1, "i");
 
-        read_and_assert_id_token(&mut lexer, 1, "=");
+        read_and_assert_operator_token(&mut lexer, 1, "=");
 
         read_and_assert_number_token(&mut lexer, 1, "1");
 
@@ -309,4 +859,218 @@ pub mod tests {
 
         read_and_assert_eof_token(&mut lexer, 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_assign_number_spans() {
+        let mut lexer = Lexer::from_str("i = 1");
+
+        let id = lexer.read().unwrap();
+        assert_eq!(*id.get_span(), Span { start: 0, end: 1, line: 1, column: 1 });
+
+        let eq = lexer.read().unwrap();
+        assert_eq!(*eq.get_span(), Span { start: 2, end: 3, line: 1, column: 3 });
+
+        let number = lexer.read().unwrap();
+        assert_eq!(*number.get_span(), Span { start: 4, end: 5, line: 1, column: 5 });
+
+        let eol = lexer.read().unwrap();
+        assert_eq!(*eol.get_span(), Span { start: 5, end: 5, line: 1, column: 6 });
+    }
+
+    #[test]
+    fn eol_span_excludes_trailing_newline() {
+        let mut lexer = Lexer::from_str("ab\ncd\n");
+
+        read_and_assert_id_token(&mut lexer, 1, "ab");
+        let eol1 = lexer.read().unwrap();
+        assert_eq!(*eol1.get_span(), Span { start: 2, end: 2, line: 1, column: 3 });
+
+        read_and_assert_id_token(&mut lexer, 2, "cd");
+        let eol2 = lexer.read().unwrap();
+        assert_eq!(*eol2.get_span(), Span { start: 5, end: 5, line: 2, column: 3 });
+    }
+
+    #[test]
+    fn read_from_reader_source() {
+        let buffer = SourceBuffer::from_reader(Cursor::new(b"i = 1".to_vec())).unwrap();
+        let mut lexer = buffer.lexer();
+
+        read_and_assert_id_token(&mut lexer, 1, "i");
+        read_and_assert_operator_token(&mut lexer, 1, "=");
+        read_and_assert_number_token(&mut lexer, 1, "1");
+        read_and_assert_eol_token(&mut lexer, 1);
+        read_and_assert_eof_token(&mut lexer, 1);
+    }
+
+    #[test]
+    fn read_invalid_number_overflows() {
+        let mut lexer = Lexer::from_str("99999999999999999999");
+
+        let result = lexer.read();
+        assert!(matches!(result, Err(LexError::InvalidNumber { .. })));
+    }
+
+    #[test]
+    fn read_unterminated_string_errors() {
+        let mut lexer = Lexer::from_str("\"abc");
+
+        let result = lexer.read();
+        assert!(matches!(result, Err(LexError::UnterminatedString { .. })));
+    }
+
+    #[test]
+    fn read_unexpected_character_errors() {
+        let mut lexer = Lexer::from_str("i 全");
+
+        read_and_assert_id_token(&mut lexer, 1, "i");
+
+        let result = lexer.read();
+        assert!(matches!(result, Err(LexError::UnexpectedCharacter { ch: '全', .. })));
+    }
+
+    #[test]
+    fn read_if_as_keyword() {
+        let mut lexer = Lexer::from_str("if while elsewhere");
+
+        let if_token = lexer.read().unwrap();
+        assert!(matches!(if_token, Token::KEYWORD { kind: Keyword::If, .. }));
+        assert!(if_token.is_keyword());
+
+        let while_token = lexer.read().unwrap();
+        assert!(matches!(while_token, Token::KEYWORD { kind: Keyword::While, .. }));
+        assert!(while_token.is_keyword());
+
+        // "elsewhere" merely starts with a keyword's letters; it's still an identifier.
+        let elsewhere = lexer.read().unwrap();
+        assert_id_token(&elsewhere, 1, "elsewhere");
+        assert!(!elsewhere.is_keyword());
+    }
+
+    #[test]
+    fn read_operators_with_precedence() {
+        let mut lexer = Lexer::from_str("a || b && c == d + e * f");
+
+        read_and_assert_id_token(&mut lexer, 1, "a");
+        let or_token = lexer.read().unwrap();
+        assert_eq!(or_token.precedence(), Some(1));
+        read_and_assert_id_token(&mut lexer, 1, "b");
+        let and_token = lexer.read().unwrap();
+        assert_eq!(and_token.precedence(), Some(2));
+        read_and_assert_id_token(&mut lexer, 1, "c");
+        let eq_token = lexer.read().unwrap();
+        assert_eq!(eq_token.precedence(), Some(3));
+        read_and_assert_id_token(&mut lexer, 1, "d");
+        let plus_token = lexer.read().unwrap();
+        assert_eq!(plus_token.precedence(), Some(4));
+        read_and_assert_id_token(&mut lexer, 1, "e");
+        let star_token = lexer.read().unwrap();
+        assert_eq!(star_token.precedence(), Some(5));
+        assert!(star_token.is_operator());
+    }
+
+    #[test]
+    fn read_one_float() {
+        let mut lexer = Lexer::from_str("3.14");
+
+        let token = lexer.read().unwrap();
+        assert_float_token(&token, 1, "3.14");
+        assert_eq!(*token.get_float().unwrap(), 3.14);
+
+        read_and_assert_eol_token(&mut lexer, 1);
+
+        read_and_assert_eof_token(&mut lexer, 1);
+    }
+
+    #[test]
+    fn read_string_decodes_escapes() {
+        let mut lexer = Lexer::from_str("\"a\\nb\\tc\\\"d\\\\e\"");
+
+        read_and_assert_string_token(&mut lexer, 1, "a\nb\tc\"d\\e");
+    }
+
+    #[test]
+    fn non_operator_punctuation_has_no_precedence() {
+        let mut lexer = Lexer::from_str("(1)");
+
+        let open_paren = lexer.read().unwrap();
+        assert_operator_token(&open_paren, 1, "(");
+        assert_eq!(open_paren.precedence(), None);
+    }
+
+    #[test]
+    fn read_interpolated_string() {
+        let mut lexer = Lexer::from_str("\"value is ${x + 1}\"");
+
+        let start = lexer.read().unwrap();
+        match &start {
+            Token::STRINGSTART { token_base } => assert_eq!(token_base.text, "value is "),
+            _ => panic!("expected STRINGSTART token, got {:?}", start),
+        }
+
+        let interp_start = lexer.read().unwrap();
+        assert!(matches!(interp_start, Token::INTERPSTART { .. }));
+
+        read_and_assert_id_token(&mut lexer, 1, "x");
+        let plus = lexer.read().unwrap();
+        assert_operator_token(&plus, 1, "+");
+        read_and_assert_number_token(&mut lexer, 1, "1");
+
+        let interp_end = lexer.read().unwrap();
+        assert!(matches!(interp_end, Token::INTERPEND { .. }));
+
+        let end = lexer.read().unwrap();
+        match &end {
+            Token::STRINGEND { token_base } => assert_eq!(token_base.text, ""),
+            _ => panic!("expected STRINGEND token, got {:?}", end),
+        }
+
+        read_and_assert_eol_token(&mut lexer, 1);
+        read_and_assert_eof_token(&mut lexer, 1);
+    }
+
+    #[test]
+    fn read_string_with_multiple_interpolations() {
+        let mut lexer = Lexer::from_str("\"a${x}b${y}c\"");
+
+        let start = lexer.read().unwrap();
+        match &start {
+            Token::STRINGSTART { token_base } => assert_eq!(token_base.text, "a"),
+            _ => panic!("expected STRINGSTART token, got {:?}", start),
+        }
+        assert!(matches!(lexer.read().unwrap(), Token::INTERPSTART { .. }));
+        read_and_assert_id_token(&mut lexer, 1, "x");
+        assert!(matches!(lexer.read().unwrap(), Token::INTERPEND { .. }));
+
+        let middle = lexer.read().unwrap();
+        match &middle {
+            Token::STRINGSTART { token_base } => assert_eq!(token_base.text, "b"),
+            _ => panic!("expected STRINGSTART token, got {:?}", middle),
+        }
+        assert!(matches!(lexer.read().unwrap(), Token::INTERPSTART { .. }));
+        read_and_assert_id_token(&mut lexer, 1, "y");
+        assert!(matches!(lexer.read().unwrap(), Token::INTERPEND { .. }));
+
+        let end = lexer.read().unwrap();
+        match &end {
+            Token::STRINGEND { token_base } => assert_eq!(token_base.text, "c"),
+            _ => panic!("expected STRINGEND token, got {:?}", end),
+        }
+    }
+
+    #[test]
+    fn read_interpolation_with_nested_braces() {
+        let mut lexer = Lexer::from_str("\"${ { 1 } }\"");
+
+        assert!(matches!(lexer.read().unwrap(), Token::STRINGSTART { .. }));
+        assert!(matches!(lexer.read().unwrap(), Token::INTERPSTART { .. }));
+
+        let open_brace = lexer.read().unwrap();
+        assert_operator_token(&open_brace, 1, "{");
+        read_and_assert_number_token(&mut lexer, 1, "1");
+        let close_brace = lexer.read().unwrap();
+        assert_operator_token(&close_brace, 1, "}");
+
+        assert!(matches!(lexer.read().unwrap(), Token::INTERPEND { .. }));
+        assert!(matches!(lexer.read().unwrap(), Token::STRINGEND { .. }));
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::LinkedList;
+use std::env;
+use std::fs::File;
+use std::process::ExitCode;
+
+mod ast;
+mod lexer;
+
+use ast::ASTree;
+use lexer::{SourceBuffer, Token};
+
+fn main() -> ExitCode {
+    let mut path = None;
+    let mut dump_tokens = false;
+    let mut dump_ast = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-t" => dump_tokens = true,
+            "-a" => dump_ast = true,
+            _ => path = Some(arg),
+        }
+    }
+
+    let Some(path) = path else {
+        eprintln!("usage: two-week-script <source-file> [-t] [-a]");
+        return ExitCode::FAILURE;
+    };
+
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("failed to open '{}': {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let buffer = match SourceBuffer::from_reader(file) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            eprintln!("failed to read '{}': {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut lexer = buffer.lexer();
+    let mut token_list = LinkedList::new();
+    loop {
+        let token = match lexer.read() {
+            Ok(token) => token,
+            Err(err) => {
+                eprintln!("lex error: {}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        if dump_tokens {
+            println!("{}", token);
+        }
+
+        let is_eof = matches!(token, Token::EOF { .. });
+        token_list.push_back(token);
+        if is_eof {
+            break;
+        }
+    }
+
+    if dump_ast {
+        print!("{}", ASTree::LIST { token_list });
+    }
+
+    ExitCode::SUCCESS
+}